@@ -7,6 +7,34 @@ impl Message for Ping {
     type Result = usize;
 }
 
+/// Define `Set` message, responds with the count it replaced
+struct Set(usize);
+
+impl Message for Set {
+    type Result = usize;
+}
+
+/// Define `Get` message
+struct Get;
+
+impl Message for Get {
+    type Result = usize;
+}
+
+/// Define `Stop` message
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+/// Define `Reset` message, responds with the count before reset
+struct Reset;
+
+impl Message for Reset {
+    type Result = usize;
+}
+
 /// Actor
 struct MyActor {
     count: usize,
@@ -27,10 +55,103 @@ impl Handler<Ping> for MyActor {
     }
 }
 
+/// Handler for `Set` message
+impl Handler<Set> for MyActor {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Set, _: &mut Context<Self>) -> Self::Result {
+        let previous = self.count;
+        self.count = msg.0;
+        previous
+    }
+}
+
+/// Handler for `Get` message
+impl Handler<Get> for MyActor {
+    type Result = usize;
+
+    fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Self::Result {
+        self.count
+    }
+}
+
+/// Handler for `Stop` message
+impl Handler<Stop> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) -> Self::Result {
+        ctx.stop();
+    }
+}
+
+/// Handler for `Reset` message
+impl Handler<Reset> for MyActor {
+    type Result = usize;
+
+    fn handle(&mut self, _: Reset, _: &mut Context<Self>) -> Self::Result {
+        let previous = self.count;
+        self.count = 0;
+        previous
+    }
+}
+
 pub async fn run() {
     let addr = MyActor { count: 10 }.start();
     let res = addr.send(Ping(10)).await;
 
     // handle() returns tokio handle
     println!("RESULT: {}", res.unwrap() == 20);
+
+    let previous = addr.send(Set(100)).await.unwrap();
+    addr.send(Ping(5)).await.unwrap();
+    let current = addr.send(Get).await.unwrap();
+    println!("SET: previous {} current {}", previous, current);
+
+    let previous = addr.send(Reset).await.unwrap();
+    println!("RESET: previous count {}", previous);
+
+    addr.send(Stop).await.unwrap();
+    // mailbox is closed once the actor has stopped
+    match addr.send(Ping(1)).await {
+        Ok(count) => println!("STILL RUNNING: {}", count),
+        Err(err) => println!("STOPPED: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix::test]
+    async fn reset_returns_previous_count() {
+        let addr = MyActor { count: 10 }.start();
+        addr.send(Ping(5)).await.unwrap();
+
+        assert_eq!(addr.send(Reset).await.unwrap(), 15);
+        assert_eq!(addr.send(Ping(1)).await.unwrap(), 1);
+    }
+
+    #[actix::test]
+    async fn stop_ends_message_processing() {
+        let addr = MyActor { count: 10 }.start();
+        assert_eq!(addr.send(Ping(1)).await.unwrap(), 11);
+
+        addr.send(Stop).await.unwrap();
+        assert!(matches!(
+            addr.send(Ping(1)).await,
+            Err(MailboxError::Closed)
+        ));
+    }
+
+    #[actix::test]
+    async fn set_ping_get_lifecycle() {
+        let addr = MyActor { count: 10 }.start();
+
+        assert_eq!(addr.send(Set(100)).await.unwrap(), 10);
+        assert_eq!(addr.send(Ping(5)).await.unwrap(), 105);
+        assert_eq!(addr.send(Get).await.unwrap(), 105);
+        // Get does not change the count
+        assert_eq!(addr.send(Get).await.unwrap(), 105);
+        assert_eq!(addr.send(Set(0)).await.unwrap(), 105);
+    }
 }
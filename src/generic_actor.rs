@@ -0,0 +1,188 @@
+use tokio::sync::{mpsc, oneshot};
+
+/// Same shape as `tokio_actor::MyActor`, but the state and the way messages
+/// change it are supplied by the caller. The handler type `H` is a parameter
+/// so the closure is stored as is instead of boxed, callers never name it
+/// since `spawn` infers it
+struct Actor<S, M, H> {
+    receiver: mpsc::Receiver<M>,
+    state: S,
+    handler: H,
+}
+
+/// Cloneable handle, the actor lives as long as any clone does
+struct ActorHandle<M> {
+    sender: mpsc::Sender<M>,
+}
+
+impl<S, M, H> Actor<S, M, H>
+where
+    S: Send + 'static,
+    M: Send + 'static,
+    H: FnMut(&mut S, M) + Send + 'static,
+{
+    /// Spawn the run loop. `init` builds the state inside the actor task,
+    /// `handler` is called with the state for every received message
+    fn spawn<F>(init: F, handler: H, capacity: usize) -> ActorHandle<M>
+    where
+        F: FnOnce() -> S + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(capacity);
+        tokio::spawn(async move {
+            let mut actor = Actor {
+                receiver,
+                state: init(),
+                handler,
+            };
+            actor.run().await
+        });
+        ActorHandle { sender }
+    }
+
+    async fn run(&mut self) {
+        while let Some(msg) = self.receiver.recv().await {
+            (self.handler)(&mut self.state, msg);
+        }
+    }
+}
+
+impl<M> Clone for ActorHandle<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<M> ActorHandle<M> {
+    /// Fire and forget
+    async fn send(&self, msg: M) {
+        let _ = self.sender.send(msg).await;
+    }
+
+    /// Request/response, `make_msg` receives the sender to put into the message
+    async fn call<R>(&self, make_msg: impl FnOnce(oneshot::Sender<R>) -> M) -> R {
+        let (send, recv) = oneshot::channel();
+
+        let _ = self.sender.send(make_msg(send)).await;
+        recv.await.expect("Actor task has been killed")
+    }
+}
+
+/// Collects lines and hands them all back on `Drain`
+enum LinesMessage {
+    Push(String),
+    Drain {
+        respond_to: oneshot::Sender<Vec<String>>,
+    },
+}
+
+pub async fn run() {
+    let lines = Actor::spawn(
+        Vec::<String>::new,
+        |lines, msg| match msg {
+            LinesMessage::Push(line) => lines.push(line),
+            LinesMessage::Drain { respond_to } => {
+                let _ = respond_to.send(std::mem::take(lines));
+            }
+        },
+        8,
+    );
+    for word in ["generic", "tokio", "actor"] {
+        lines.send(LinesMessage::Push(word.to_string())).await;
+    }
+
+    let drained = lines
+        .call(|respond_to| LinesMessage::Drain { respond_to })
+        .await;
+    log::info!("drained: {:?}", drained);
+    let drained = lines
+        .call(|respond_to| LinesMessage::Drain { respond_to })
+        .await;
+    log::info!("drained again: {:?}", drained);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    enum CounterMessage {
+        Add(u64),
+        Get { respond_to: oneshot::Sender<u64> },
+    }
+
+    enum KvMessage {
+        Set {
+            key: String,
+            value: String,
+        },
+        Get {
+            key: String,
+            respond_to: oneshot::Sender<Option<String>>,
+        },
+    }
+
+    #[tokio::test]
+    async fn counter_actor() {
+        let counter = Actor::spawn(
+            || 0u64,
+            |count, msg| match msg {
+                CounterMessage::Add(value) => *count += value,
+                CounterMessage::Get { respond_to } => {
+                    let _ = respond_to.send(*count);
+                }
+            },
+            8,
+        );
+
+        for value in 1..=10 {
+            counter.send(CounterMessage::Add(value)).await;
+        }
+        let count = counter
+            .call(|respond_to| CounterMessage::Get { respond_to })
+            .await;
+        assert_eq!(count, 55);
+    }
+
+    #[tokio::test]
+    async fn key_value_actor() {
+        let kv = Actor::spawn(
+            HashMap::<String, String>::new,
+            |store, msg| match msg {
+                KvMessage::Set { key, value } => {
+                    store.insert(key, value);
+                }
+                KvMessage::Get { key, respond_to } => {
+                    let _ = respond_to.send(store.get(&key).cloned());
+                }
+            },
+            8,
+        );
+        let get = |key: &str| {
+            let key = key.to_string();
+            move |respond_to| KvMessage::Get { key, respond_to }
+        };
+
+        assert_eq!(kv.call(get("actor")).await, None);
+
+        // clones talk to the same actor and its state
+        let writer = kv.clone();
+        writer
+            .send(KvMessage::Set {
+                key: "actor".to_string(),
+                value: "generic".to_string(),
+            })
+            .await;
+        assert_eq!(kv.call(get("actor")).await, Some("generic".to_string()));
+
+        writer
+            .send(KvMessage::Set {
+                key: "actor".to_string(),
+                value: "updated".to_string(),
+            })
+            .await;
+        assert_eq!(kv.call(get("actor")).await, Some("updated".to_string()));
+        assert_eq!(kv.call(get("missing")).await, None);
+    }
+}
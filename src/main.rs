@@ -1,6 +1,7 @@
 use clap::{Parser, Subcommand};
 
 mod actix_actor;
+mod generic_actor;
 mod tiny_actor;
 mod tokio_actor;
 
@@ -13,10 +14,12 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::enum_variant_names)]
 enum CliCommands {
     TokioActor,
     TinyActor,
     ActixActor,
+    GenericActor,
 }
 
 fn main() {
@@ -57,5 +60,16 @@ fn main() {
             system.run().unwrap();
             actix::System::current().stop();
         }
+        // Generic tokio actor
+        CliCommands::GenericActor => {
+            log::info!("Generic actor...");
+            let rt = tokio::runtime::Runtime::new().unwrap();
+
+            rt.block_on(async {
+                println!("now running on a worker thread");
+
+                generic_actor::run().await;
+            });
+        }
     }
 }
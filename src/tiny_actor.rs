@@ -2,7 +2,7 @@ use tiny_tokio_actor::*;
 
 // The event message you may want to publish to the system event bus.
 #[derive(Clone, Debug)]
-struct TestEvent(String);
+struct TestEvent(#[allow(dead_code)] String);
 
 // Mark the struct as a system event message.
 impl SystemEvent for TestEvent {}
@@ -19,7 +19,7 @@ impl Actor<TestEvent> for TestActor {}
 // The message the actor will expect. It must derive Clone.
 // Debug is not required.
 #[derive(Clone, Debug)]
-struct TestMessage(String);
+struct TestMessage(#[allow(dead_code)] String);
 
 // Mark the message struct as an actor message. Note that we
 // also define the response we expect back from this message.
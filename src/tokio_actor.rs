@@ -1,40 +1,127 @@
 use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
 
 #[derive(Debug)]
 struct MyActor {
     receiver: mpsc::Receiver<ActorMessage>,
-    next_id: u32,
+    next_id: u64,
+    processed: u64,
+}
+
+/// Snapshot of the actor progress
+#[derive(Debug)]
+struct ActorStats {
+    processed: u64,
+    next_id: u64,
 }
 
 #[derive(Debug)]
 enum ActorMessage {
-    GetUniqueId { respond_to: oneshot::Sender<u32> },
+    /// Replies with `None` once the id space is exhausted
+    GetUniqueId {
+        respond_to: oneshot::Sender<Option<u64>>,
+    },
+    Reset {
+        respond_to: oneshot::Sender<()>,
+    },
+    ReserveRange {
+        count: u64,
+        respond_to: oneshot::Sender<Option<u64>>,
+    },
+    GetStats {
+        respond_to: oneshot::Sender<ActorStats>,
+    },
+    Shutdown {
+        respond_to: oneshot::Sender<()>,
+    },
+}
+
+/// Actor task is gone, e.g. another clone of the handle shut it down
+#[derive(Debug, PartialEq)]
+struct Closed;
+
+/// Why a non-blocking request could not be served
+#[derive(Debug)]
+enum TrySendError {
+    /// Mailbox is at capacity, the request was not queued
+    Full,
+    /// Actor task is gone, either before or after accepting the request
+    Closed,
 }
 
+/// The actor runs until every clone of the handle is dropped or `shutdown`
+/// is called, weak handles do not count
 #[derive(Clone, Debug)]
 struct MyActorHandle {
     sender: mpsc::Sender<ActorMessage>,
 }
 
+/// Handle that does not keep the actor alive
+#[derive(Clone, Debug)]
+struct WeakActorHandle {
+    sender: mpsc::WeakSender<ActorMessage>,
+}
+
 impl MyActor {
-    fn new(receiver: mpsc::Receiver<ActorMessage>) -> Self {
+    fn new(receiver: mpsc::Receiver<ActorMessage>, next_id: u64) -> Self {
         MyActor {
             receiver,
-            next_id: 0,
+            next_id,
+            processed: 0,
         }
     }
 
     async fn run(&mut self) {
         while let Some(msg) = self.receiver.recv().await {
-            self.handle_message(msg).await;
+            if !self.handle_message(msg).await {
+                break;
+            }
         }
     }
 
-    async fn handle_message(&mut self, msg: ActorMessage) {
+    /// Returns `false` when the actor should stop its run loop
+    async fn handle_message(&mut self, msg: ActorMessage) -> bool {
+        self.processed += 1;
         match msg {
             ActorMessage::GetUniqueId { respond_to } => {
-                self.next_id += 1;
-                let _ = respond_to.send(self.next_id);
+                // never wrap around into already issued ids
+                let id = self.next_id.checked_add(1);
+                if let Some(id) = id {
+                    self.next_id = id;
+                }
+                let _ = respond_to.send(id);
+                true
+            }
+            ActorMessage::Reset { respond_to } => {
+                self.next_id = 0;
+                let _ = respond_to.send(());
+                true
+            }
+            ActorMessage::ReserveRange { count, respond_to } => {
+                // ids are handed out starting from next_id + 1, an empty
+                // block or one that does not fit is refused
+                let start = match (self.next_id.checked_add(1), self.next_id.checked_add(count)) {
+                    (Some(start), Some(last)) if count > 0 => {
+                        self.next_id = last;
+                        Some(start)
+                    }
+                    _ => None,
+                };
+                let _ = respond_to.send(start);
+                true
+            }
+            ActorMessage::GetStats { respond_to } => {
+                let _ = respond_to.send(ActorStats {
+                    processed: self.processed,
+                    next_id: self.next_id,
+                });
+                true
+            }
+            ActorMessage::Shutdown { respond_to } => {
+                // refuse new requests before the caller learns about shutdown
+                self.receiver.close();
+                let _ = respond_to.send(());
+                false
             }
         }
     }
@@ -42,24 +129,361 @@ impl MyActor {
 
 impl MyActorHandle {
     pub fn new() -> Self {
-        let (sender, receiver) = mpsc::channel(8);
-        let mut actor = MyActor::new(receiver);
-        tokio::spawn(async move { actor.run().await });
-        Self { sender }
+        Self::with_capacity(8)
+    }
+
+    /// Spawn the actor with a mailbox holding up to `capacity` messages.
+    /// Panics if `capacity` is 0
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::spawn(capacity, 0).0
+    }
+
+    /// Spawn the actor as if `next_id` ids were already issued, lets tests
+    /// reach the end of the id space
+    #[cfg(test)]
+    fn starting_at(next_id: u64) -> Self {
+        Self::spawn(8, next_id).0
+    }
+
+    /// Also returns the actor task so it can be awaited
+    fn spawn(capacity: usize, next_id: u64) -> (Self, JoinHandle<()>) {
+        assert!(capacity > 0, "actor mailbox capacity must be at least 1");
+        let (sender, receiver) = mpsc::channel(capacity);
+        let mut actor = MyActor::new(receiver, next_id);
+        let task = tokio::spawn(async move { actor.run().await });
+        (Self { sender }, task)
     }
 
-    async fn get_unique_id(&self) -> u32 {
+    pub fn downgrade(&self) -> WeakActorHandle {
+        WeakActorHandle {
+            sender: self.sender.downgrade(),
+        }
+    }
+
+    /// Mailbox size the actor was created with
+    pub fn capacity(&self) -> usize {
+        self.sender.max_capacity()
+    }
+
+    /// `None` means every u64 id has been issued already
+    async fn get_unique_id(&self) -> Result<Option<u64>, Closed> {
         let (send, recv) = oneshot::channel();
         let msg = ActorMessage::GetUniqueId { respond_to: send };
 
         // send message
-        let _ = self.sender.send(msg).await;
-        recv.await.expect("Actor task has been killed")
+        self.sender.send(msg).await.map_err(|_| Closed)?;
+        recv.await.map_err(|_| Closed)
+    }
+
+    /// Like `get_unique_id`, but fails right away instead of waiting for
+    /// room in the mailbox. Waits for the reply with a blocking receive, so
+    /// it must be called outside of the async context (e.g. `spawn_blocking`)
+    fn try_get_unique_id(&self) -> Result<Option<u64>, TrySendError> {
+        let (send, recv) = oneshot::channel();
+        let msg = ActorMessage::GetUniqueId { respond_to: send };
+
+        self.sender.try_send(msg).map_err(|err| match err {
+            mpsc::error::TrySendError::Full(_) => TrySendError::Full,
+            mpsc::error::TrySendError::Closed(_) => TrySendError::Closed,
+        })?;
+        recv.blocking_recv().map_err(|_| TrySendError::Closed)
+    }
+
+    /// Start handing out ids from the beginning again
+    async fn reset(&self) -> Result<(), Closed> {
+        let (send, recv) = oneshot::channel();
+        let msg = ActorMessage::Reset { respond_to: send };
+
+        self.sender.send(msg).await.map_err(|_| Closed)?;
+        recv.await.map_err(|_| Closed)
+    }
+
+    /// Reserve `count` consecutive ids, returns the first one of the block.
+    /// `None` if `count` is 0 or the block does not fit into the id space
+    async fn reserve_range(&self, count: u64) -> Result<Option<u64>, Closed> {
+        let (send, recv) = oneshot::channel();
+        let msg = ActorMessage::ReserveRange {
+            count,
+            respond_to: send,
+        };
+
+        self.sender.send(msg).await.map_err(|_| Closed)?;
+        recv.await.map_err(|_| Closed)
+    }
+
+    /// Number of processed messages (this request included) and current id
+    async fn stats(&self) -> Result<ActorStats, Closed> {
+        let (send, recv) = oneshot::channel();
+        let msg = ActorMessage::GetStats { respond_to: send };
+
+        self.sender.send(msg).await.map_err(|_| Closed)?;
+        recv.await.map_err(|_| Closed)
+    }
+
+    /// Ask the actor to leave its run loop and wait until it acknowledges.
+    /// Requests made afterwards through other clones fail with `Closed`
+    pub async fn shutdown(self) {
+        let (send, recv) = oneshot::channel();
+        let msg = ActorMessage::Shutdown { respond_to: send };
+
+        // actor may already be gone, nothing to wait for then
+        if self.sender.send(msg).await.is_ok() {
+            let _ = recv.await;
+        }
+    }
+}
+
+impl WeakActorHandle {
+    /// `None` once the actor no longer takes requests: after `shutdown`
+    /// returns, or as soon as the last strong handle is dropped (the actor
+    /// may still be draining messages queued before that)
+    pub fn upgrade(&self) -> Option<MyActorHandle> {
+        self.sender
+            .upgrade()
+            .filter(|sender| !sender.is_closed())
+            .map(|sender| MyActorHandle { sender })
     }
 }
 
 pub async fn run() {
     let handle = MyActorHandle::new();
-    let unique_id = handle.get_unique_id().await;
-    log::info!("{:?} unique_id: {}", handle, unique_id);
+    let unique_id = handle.get_unique_id().await.unwrap();
+    log::info!(
+        "unique_id: {:?} (capacity {})",
+        unique_id,
+        handle.capacity()
+    );
+
+    let start = handle.reserve_range(10).await.unwrap();
+    log::info!("reserved 10 ids starting at {:?}", start);
+    log::info!(
+        "next unique_id: {:?}",
+        handle.get_unique_id().await.unwrap()
+    );
+
+    let blocking_handle = handle.clone();
+    let result = tokio::task::spawn_blocking(move || blocking_handle.try_get_unique_id())
+        .await
+        .unwrap();
+    log::info!("try_get_unique_id: {:?}", result);
+
+    handle.reset().await.unwrap();
+    log::info!(
+        "unique_id after reset: {:?}",
+        handle.get_unique_id().await.unwrap()
+    );
+
+    let stats = handle.stats().await.unwrap();
+    log::info!("processed: {} next_id: {}", stats.processed, stats.next_id);
+
+    let late_handle = handle.clone();
+    handle.shutdown().await;
+    log::info!("actor stopped");
+    log::info!(
+        "unique_id after shutdown: {:?}",
+        late_handle.get_unique_id().await
+    );
+
+    let big_handle = MyActorHandle::with_capacity(64);
+    log::info!("actor with capacity {}", big_handle.capacity());
+    big_handle.shutdown().await;
+
+    let strong_handle = MyActorHandle::new();
+    let weak_handle = strong_handle.downgrade();
+    if let Some(upgraded) = weak_handle.upgrade() {
+        log::info!(
+            "upgraded unique_id: {:?}",
+            upgraded.get_unique_id().await.unwrap()
+        );
+    }
+    drop(strong_handle);
+    log::info!("weak handle alive: {}", weak_handle.upgrade().is_some());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    /// Fails the test instead of hanging if the actor task keeps running
+    async fn assert_stopped(task: JoinHandle<()>) {
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("actor task is still running")
+            .expect("actor task panicked");
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_actor_task() {
+        let (handle, task) = MyActorHandle::spawn(8, 0);
+        assert_eq!(handle.get_unique_id().await.unwrap(), Some(1));
+
+        handle.shutdown().await;
+        assert_stopped(task).await;
+    }
+
+    #[tokio::test]
+    async fn single_and_bulk_ids_never_repeat() {
+        let handle = MyActorHandle::new();
+        let mut issued = HashSet::new();
+
+        for count in [3, 1, 10, 2] {
+            let id = handle.get_unique_id().await.unwrap().unwrap();
+            assert!(issued.insert(id), "id {} issued twice", id);
+
+            let start = handle.reserve_range(count).await.unwrap().unwrap();
+            for id in start..start + count {
+                assert!(issued.insert(id), "id {} issued twice", id);
+            }
+        }
+        let id = handle.get_unique_id().await.unwrap().unwrap();
+        assert!(issued.insert(id), "id {} issued twice", id);
+        assert_eq!(issued.len(), 21);
+    }
+
+    #[tokio::test]
+    async fn empty_range_is_refused() {
+        let handle = MyActorHandle::new();
+
+        assert_eq!(handle.reserve_range(0).await.unwrap(), None);
+        assert_eq!(handle.get_unique_id().await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn try_get_unique_id_reports_full_mailbox() {
+        let (handle, _task) = MyActorHandle::spawn(1, 0);
+        // a reserved slot is never handed to the actor, so the mailbox stays
+        // full no matter how fast the actor drains it
+        let _parked = handle.sender.reserve().await.unwrap();
+
+        let blocking_handle = handle.clone();
+        let result = tokio::task::spawn_blocking(move || blocking_handle.try_get_unique_id())
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(TrySendError::Full)));
+    }
+
+    #[tokio::test]
+    async fn try_get_unique_id_reports_dead_actor() {
+        let (handle, task) = MyActorHandle::spawn(1, 0);
+        handle.clone().shutdown().await;
+        assert_stopped(task).await;
+
+        let result = tokio::task::spawn_blocking(move || handle.try_get_unique_id())
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(TrySendError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn larger_capacity_queues_more_requests() {
+        // the test runtime is single threaded, the actors do not get to
+        // drain their mailboxes until the test yields
+        fn fill(handle: &MyActorHandle) -> usize {
+            let mut queued = 0;
+            loop {
+                let (send, _recv) = oneshot::channel();
+                let msg = ActorMessage::GetUniqueId { respond_to: send };
+                match handle.sender.try_send(msg) {
+                    Ok(()) => queued += 1,
+                    Err(_) => return queued,
+                }
+            }
+        }
+
+        let small = MyActorHandle::with_capacity(2);
+        let large = MyActorHandle::with_capacity(16);
+        assert_eq!(small.capacity(), 2);
+        assert_eq!(large.capacity(), 16);
+
+        assert_eq!(fill(&small), 2);
+        assert_eq!(fill(&large), 16);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    async fn zero_capacity_is_rejected() {
+        MyActorHandle::with_capacity(0);
+    }
+
+    #[tokio::test]
+    async fn stats_count_processed_messages() {
+        let handle = MyActorHandle::new();
+        for _ in 0..5 {
+            handle.get_unique_id().await.unwrap();
+        }
+
+        // the stats request itself is counted as processed
+        let stats = handle.stats().await.unwrap();
+        assert_eq!(stats.processed, 6);
+        assert_eq!(stats.next_id, 5);
+
+        let stats = handle.stats().await.unwrap();
+        assert_eq!(stats.processed, 7);
+        assert_eq!(stats.next_id, 5);
+    }
+
+    #[tokio::test]
+    async fn exhausted_ids_are_reported() {
+        let handle = MyActorHandle::starting_at(u64::MAX - 1);
+
+        assert_eq!(handle.get_unique_id().await.unwrap(), Some(u64::MAX));
+        assert_eq!(handle.get_unique_id().await.unwrap(), None);
+        assert_eq!(handle.reserve_range(1).await.unwrap(), None);
+        assert_eq!(handle.stats().await.unwrap().next_id, u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn reservation_past_the_end_is_refused() {
+        let handle = MyActorHandle::starting_at(u64::MAX - 3);
+
+        // refused block must not move next_id
+        assert_eq!(handle.reserve_range(4).await.unwrap(), None);
+        assert_eq!(handle.reserve_range(3).await.unwrap(), Some(u64::MAX - 2));
+        assert_eq!(handle.get_unique_id().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn weak_handle_does_not_keep_actor_alive() {
+        let (handle, task) = MyActorHandle::spawn(8, 0);
+        let weak_handle = handle.downgrade();
+
+        let upgraded = weak_handle.upgrade().expect("strong handle is alive");
+        assert_eq!(upgraded.get_unique_id().await.unwrap(), Some(1));
+        drop(upgraded);
+
+        drop(handle);
+        assert!(weak_handle.upgrade().is_none());
+        assert_stopped(task).await;
+        // weak handle outlives the actor
+        assert!(weak_handle.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn clones_fail_after_shutdown() {
+        let (handle, task) = MyActorHandle::spawn(8, 0);
+        let other = handle.clone();
+
+        handle.shutdown().await;
+        assert_eq!(other.get_unique_id().await, Err(Closed));
+        assert_eq!(other.reserve_range(1).await, Err(Closed));
+        assert_eq!(other.reset().await, Err(Closed));
+        assert!(other.stats().await.is_err());
+        assert_stopped(task).await;
+    }
+
+    #[tokio::test]
+    async fn weak_handle_does_not_upgrade_after_shutdown() {
+        let (handle, task) = MyActorHandle::spawn(8, 0);
+        let strong_clone = handle.clone();
+        let weak_handle = handle.downgrade();
+
+        handle.shutdown().await;
+        // a strong clone is still alive, but the actor is not
+        assert!(weak_handle.upgrade().is_none());
+        assert_stopped(task).await;
+        drop(strong_clone);
+        assert!(weak_handle.upgrade().is_none());
+    }
 }